@@ -1,12 +1,14 @@
 use anyhow::{Context, Result};
-use clap::{Parser, ValueEnum};
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum, ValueSource};
 use ignore::{overrides::OverrideBuilder, WalkBuilder};
 use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 use regex::Regex;
-use serde::Serialize;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::{self, BufWriter, Write}; // Added BufWriter
 use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
@@ -15,12 +17,57 @@ use std::time::Instant;
 // --- Configuration & Constants ---
 const CHARS_PER_TOKEN: usize = 4;
 const MAX_FILE_SIZE_BYTES: u64 = 1_000_000; // 1MB Limit for full context
+const CHUNK_LINES: usize = 40; // Line granularity for RAG-mode relevance ranking
+const EMBEDDING_CACHE_FILE: &str = ".codecontexter_embed_cache.json";
+const CONFIG_FILE_NAME: &str = ".codecontexter.toml";
 
-#[derive(ValueEnum, Clone, Debug, PartialEq)]
+#[derive(ValueEnum, Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum OutputFormat {
     Markdown,
     Json,
     Xml,
+    Html,
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Tokenizer {
+    /// `content.len() / CHARS_PER_TOKEN` heuristic; no extra dependency loaded.
+    None,
+    Cl100kBase,
+    O200kBase,
+}
+
+impl Tokenizer {
+    fn name(&self) -> &'static str {
+        match self {
+            Tokenizer::None => "none",
+            Tokenizer::Cl100kBase => "cl100k_base",
+            Tokenizer::O200kBase => "o200k_base",
+        }
+    }
+
+    /// Counts tokens in `content`. `tiktoken_rs` returns a `Result` when loading the BPE ranks
+    /// for `cl100k_base`/`o200k_base`, so a load failure surfaces as a normal error instead of
+    /// panicking the whole run.
+    fn count_tokens(&self, content: &str) -> Result<usize> {
+        match self {
+            Tokenizer::None => Ok(content.len() / CHARS_PER_TOKEN),
+            Tokenizer::Cl100kBase => {
+                static BPE: OnceLock<std::result::Result<tiktoken_rs::CoreBPE, String>> = OnceLock::new();
+                let bpe = BPE.get_or_init(|| tiktoken_rs::cl100k_base().map_err(|e| e.to_string()));
+                let bpe = bpe.as_ref().map_err(|e| anyhow::anyhow!("Failed to load cl100k_base tokenizer: {}", e))?;
+                Ok(bpe.encode_with_special_tokens(content).len())
+            }
+            Tokenizer::O200kBase => {
+                static BPE: OnceLock<std::result::Result<tiktoken_rs::CoreBPE, String>> = OnceLock::new();
+                let bpe = BPE.get_or_init(|| tiktoken_rs::o200k_base().map_err(|e| e.to_string()));
+                let bpe = bpe.as_ref().map_err(|e| anyhow::anyhow!("Failed to load o200k_base tokenizer: {}", e))?;
+                Ok(bpe.encode_with_special_tokens(content).len())
+            }
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -53,6 +100,156 @@ struct Args {
     /// Show verbose logging
     #[arg(short, long)]
     verbose: bool,
+
+    /// Only include content relevant to this query (enables RAG mode)
+    #[arg(long)]
+    query: Option<String>,
+
+    /// Token budget for RAG mode; chunks are added by descending relevance until this is exceeded
+    #[arg(long, default_value_t = 8000)]
+    max_tokens: usize,
+
+    /// Tokenizer used to count tokens precisely instead of the chars/4 heuristic
+    #[arg(long, value_enum, default_value_t = Tokenizer::None)]
+    tokenizer: Tokenizer,
+
+    /// Path to a `.codecontexter.toml` config file (defaults to one in the scan root, if present)
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Cap on total bytes of file content held in memory during the crawl, in megabytes
+    #[arg(long)]
+    max_crawl_memory: Option<u64>,
+
+    /// Include gitignored files in the scan
+    #[arg(long)]
+    all_files: bool,
+
+    /// Only include these languages, e.g. --type rust,python (see --type-list)
+    #[arg(long = "type", value_delimiter = ',')]
+    type_filter: Vec<String>,
+
+    /// Exclude these languages, e.g. --type-not markdown,json (see --type-list)
+    #[arg(long = "type-not", value_delimiter = ',')]
+    type_not: Vec<String>,
+
+    /// Print the supported --type/--type-not language table and exit
+    #[arg(long)]
+    type_list: bool,
+
+    /// Only scan files changed since this git ref (e.g. HEAD~5, a branch, or a tag)
+    #[arg(long)]
+    since: Option<String>,
+}
+
+/// Mirrors the overridable subset of `Args`, loaded from `.codecontexter.toml`. CLI flags left
+/// at their built-in default are filled in from here; a flag explicitly set on the command line
+/// always wins.
+#[derive(Debug, Default, Deserialize)]
+struct FileConfig {
+    output: Option<PathBuf>,
+    format: Option<OutputFormat>,
+    exclude: Option<Vec<String>>,
+    force: Option<bool>,
+    verbose: Option<bool>,
+    query: Option<String>,
+    max_tokens: Option<usize>,
+    tokenizer: Option<Tokenizer>,
+    crawl: Option<CrawlConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CrawlConfig {
+    max_crawl_memory: Option<u64>,
+    all_files: Option<bool>,
+}
+
+/// Records which `Args` fields the user actually typed on the command line, as opposed to ones
+/// clap filled in from its `default_value`/`default_value_t`. `apply_file_config` needs this
+/// distinction to tell "left at the default" apart from "explicitly set to the same value as the
+/// default" — comparing the parsed value against the hardcoded default can't make that call.
+#[derive(Debug, Default)]
+struct ExplicitFlags {
+    output: bool,
+    format: bool,
+    exclude: bool,
+    force: bool,
+    verbose: bool,
+    max_tokens: bool,
+    tokenizer: bool,
+    all_files: bool,
+}
+
+impl ExplicitFlags {
+    fn from_matches(matches: &clap::ArgMatches) -> Self {
+        let explicit = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        ExplicitFlags {
+            output: explicit("output"),
+            format: explicit("format"),
+            exclude: explicit("exclude"),
+            force: explicit("force"),
+            verbose: explicit("verbose"),
+            max_tokens: explicit("max_tokens"),
+            tokenizer: explicit("tokenizer"),
+            all_files: explicit("all_files"),
+        }
+    }
+}
+
+/// Parses `Args` from `itr` the way [`clap::Parser::parse_from`] would, but also returns which
+/// flags were explicitly passed (see `ExplicitFlags`), which `parse_from` throws away.
+fn parse_args_from<I, T>(itr: I) -> (Args, ExplicitFlags)
+where
+    I: IntoIterator<Item = T>,
+    T: Into<std::ffi::OsString> + Clone,
+{
+    let matches = Args::command().get_matches_from(itr);
+    let flags = ExplicitFlags::from_matches(&matches);
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    (args, flags)
+}
+
+fn load_file_config(args: &Args, root: &Path) -> Result<FileConfig> {
+    let config_path = args.config.clone().unwrap_or_else(|| root.join(CONFIG_FILE_NAME));
+    if !config_path.exists() {
+        return Ok(FileConfig::default());
+    }
+    let raw = std::fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read config file: {}", config_path.display()))?;
+    toml::from_str(&raw).with_context(|| format!("Failed to parse config file: {}", config_path.display()))
+}
+
+fn apply_file_config(args: &mut Args, config: FileConfig, explicit: &ExplicitFlags) {
+    if !explicit.output {
+        if let Some(output) = config.output { args.output = output; }
+    }
+    if !explicit.format {
+        if let Some(format) = config.format { args.format = format; }
+    }
+    if !explicit.exclude {
+        if let Some(exclude) = config.exclude { args.exclude = exclude; }
+    }
+    if !explicit.force {
+        if let Some(force) = config.force { args.force = force; }
+    }
+    if !explicit.verbose {
+        if let Some(verbose) = config.verbose { args.verbose = verbose; }
+    }
+    if args.query.is_none() {
+        args.query = config.query;
+    }
+    if !explicit.max_tokens {
+        if let Some(max_tokens) = config.max_tokens { args.max_tokens = max_tokens; }
+    }
+    if !explicit.tokenizer {
+        if let Some(tokenizer) = config.tokenizer { args.tokenizer = tokenizer; }
+    }
+    if let Some(crawl) = config.crawl {
+        if args.max_crawl_memory.is_none() { args.max_crawl_memory = crawl.max_crawl_memory; }
+        if !explicit.all_files {
+            if let Some(all_files) = crawl.all_files { args.all_files = all_files; }
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -63,6 +260,239 @@ struct FileArtifact {
     content: String,
     token_estimate: usize,
     is_truncated: bool,
+    // Chunked view of `content` used only by RAG mode (--query); never serialized so the
+    // default full-dump output contract is unchanged.
+    #[serde(skip)]
+    chunks: Vec<Chunk>,
+}
+
+// --- RAG Mode: Chunking, Embeddings, Relevance Ranking ---
+
+#[derive(Debug, Clone)]
+struct Chunk {
+    byte_start: usize,
+    byte_end: usize,
+    content: String,
+    token_estimate: usize,
+}
+
+fn chunk_content(content: &str, tokenizer: &Tokenizer) -> Result<Vec<Chunk>> {
+    let mut chunks = Vec::new();
+    let mut lines_in_chunk = 0;
+    let mut chunk_start = 0usize;
+    let mut offset = 0usize;
+
+    for line in content.split_inclusive('\n') {
+        if lines_in_chunk == 0 {
+            chunk_start = offset;
+        }
+        lines_in_chunk += 1;
+        offset += line.len();
+
+        if lines_in_chunk >= CHUNK_LINES {
+            let slice = &content[chunk_start..offset];
+            chunks.push(Chunk {
+                byte_start: chunk_start,
+                byte_end: offset,
+                content: slice.to_string(),
+                token_estimate: tokenizer.count_tokens(slice)?,
+            });
+            lines_in_chunk = 0;
+        }
+    }
+
+    if lines_in_chunk > 0 {
+        let slice = &content[chunk_start..offset];
+        chunks.push(Chunk {
+            byte_start: chunk_start,
+            byte_end: offset,
+            content: slice.to_string(),
+            token_estimate: tokenizer.count_tokens(slice)?,
+        });
+    }
+
+    Ok(chunks)
+}
+
+fn hash_content(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+type EmbeddingCache = HashMap<u64, Vec<f32>>;
+
+fn embedding_cache_path(root: &Path) -> PathBuf {
+    root.join(EMBEDDING_CACHE_FILE)
+}
+
+fn load_embedding_cache(root: &Path) -> EmbeddingCache {
+    std::fs::read_to_string(embedding_cache_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_embedding_cache(root: &Path, cache: &EmbeddingCache) {
+    if let Ok(raw) = serde_json::to_string(cache) {
+        let _ = std::fs::write(embedding_cache_path(root), raw);
+    }
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponseData {
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingResponseData>,
+}
+
+/// Embeds `text` via an OpenAI-compatible `/embeddings` endpoint configured through
+/// `CODECONTEXTER_EMBEDDINGS_URL` (and optionally `CODECONTEXTER_EMBEDDINGS_API_KEY` /
+/// `CODECONTEXTER_EMBEDDINGS_MODEL`). Falls back to `fastembed`'s default local model when no
+/// endpoint is configured, so RAG mode needs no embeddings service.
+fn fetch_embedding(text: &str) -> Result<Vec<f32>> {
+    if let Ok(url) = std::env::var("CODECONTEXTER_EMBEDDINGS_URL") {
+        let model = std::env::var("CODECONTEXTER_EMBEDDINGS_MODEL")
+            .unwrap_or_else(|_| "text-embedding-3-small".to_string());
+        let client = reqwest::blocking::Client::new();
+        let mut request = client.post(&url).json(&EmbeddingRequest { model: &model, input: text });
+        if let Ok(key) = std::env::var("CODECONTEXTER_EMBEDDINGS_API_KEY") {
+            request = request.bearer_auth(key);
+        }
+        let response: EmbeddingResponse = request
+            .send()
+            .context("Failed to reach embeddings endpoint")?
+            .error_for_status()
+            .context("Embeddings endpoint returned an error")?
+            .json()
+            .context("Failed to parse embeddings response")?;
+        response
+            .data
+            .into_iter()
+            .next()
+            .map(|d| d.embedding)
+            .context("Embeddings response contained no data")
+    } else {
+        static LOCAL_MODEL: OnceLock<std::result::Result<fastembed::TextEmbedding, String>> = OnceLock::new();
+        let model = LOCAL_MODEL.get_or_init(|| {
+            fastembed::TextEmbedding::try_new(Default::default()).map_err(|e| e.to_string())
+        });
+        let model = model
+            .as_ref()
+            .map_err(|e| anyhow::anyhow!("Failed to load local embedding model: {}", e))?;
+        let embeddings = model
+            .embed(vec![text], None)
+            .context("Local embedding inference failed")?;
+        embeddings.into_iter().next().context("Local embedding model returned no output")
+    }
+}
+
+fn embed_cached(text: &str, cache: &mut EmbeddingCache) -> Result<Vec<f32>> {
+    let key = hash_content(text);
+    if let Some(vector) = cache.get(&key) {
+        return Ok(vector.clone());
+    }
+    let vector = fetch_embedding(text)?;
+    cache.insert(key, vector.clone());
+    Ok(vector)
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 { 0.0 } else { dot / (norm_a * norm_b) }
+}
+
+/// Given `chunk_tokens` in descending relevance order, greedily marks chunks as kept, stopping
+/// once the running total already exceeds `max_tokens` (so the chunk that tips the budget over
+/// is itself still kept — the cap only ever skips chunks *after* that one). Pulled out of
+/// `apply_rag_selection` so the budget math can be tested without going through embeddings.
+fn select_within_budget(chunk_tokens: &[usize], max_tokens: usize) -> Vec<bool> {
+    let mut kept = vec![false; chunk_tokens.len()];
+    let mut token_sum = 0usize;
+    for (i, &tokens) in chunk_tokens.iter().enumerate() {
+        if token_sum > max_tokens {
+            break;
+        }
+        token_sum += tokens;
+        kept[i] = true;
+    }
+    kept
+}
+
+/// Ranks the chunks of `artifacts` by relevance to `query` and keeps chunks (in descending
+/// similarity order) up to `max_tokens` — see `select_within_budget` for the exact (inclusive)
+/// cutoff. Each retained file's `content` is rebuilt from just its selected chunks, so the
+/// existing writers don't need to know about RAG mode. Retained files keep their original
+/// order; they are not reordered by relevance.
+fn apply_rag_selection(artifacts: Vec<FileArtifact>, query: &str, max_tokens: usize, root: &Path, tokenizer: &Tokenizer) -> Result<Vec<FileArtifact>> {
+    let mut cache = load_embedding_cache(root);
+    let query_embedding = embed_cached(query, &mut cache)?;
+
+    struct RankedChunk {
+        file_index: usize,
+        chunk_index: usize,
+        similarity: f32,
+    }
+
+    let mut ranked = Vec::new();
+    for (file_index, artifact) in artifacts.iter().enumerate() {
+        for (chunk_index, chunk) in artifact.chunks.iter().enumerate() {
+            let similarity = if artifact.is_truncated {
+                f32::MIN // Rank truncated files last; never worth embedding.
+            } else {
+                cosine_similarity(&embed_cached(&chunk.content, &mut cache)?, &query_embedding)
+            };
+            ranked.push(RankedChunk { file_index, chunk_index, similarity });
+        }
+    }
+    save_embedding_cache(root, &cache);
+
+    ranked.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal));
+
+    let chunk_tokens: Vec<usize> = ranked
+        .iter()
+        .map(|r| artifacts[r.file_index].chunks[r.chunk_index].token_estimate)
+        .collect();
+    let kept = select_within_budget(&chunk_tokens, max_tokens);
+
+    let mut selected: Vec<HashSet<usize>> = vec![HashSet::new(); artifacts.len()];
+    for (i, r) in ranked.iter().enumerate() {
+        if kept[i] {
+            selected[r.file_index].insert(r.chunk_index);
+        }
+    }
+
+    let mut rebuilt = Vec::new();
+    for (file_index, mut artifact) in artifacts.into_iter().enumerate() {
+        let kept = &selected[file_index];
+        if kept.is_empty() {
+            continue;
+        }
+        let mut indices: Vec<usize> = kept.iter().copied().collect();
+        indices.sort_unstable();
+        let content: String = indices
+            .iter()
+            .map(|i| artifact.chunks[*i].content.as_str())
+            .collect::<Vec<_>>()
+            .join("");
+        artifact.lines = content.lines().count();
+        artifact.token_estimate = tokenizer.count_tokens(&content)?;
+        artifact.content = content;
+        rebuilt.push(artifact);
+    }
+
+    Ok(rebuilt)
 }
 
 #[derive(Serialize)]
@@ -79,6 +509,9 @@ struct Metadata {
     total_files: usize,
     total_tokens: usize,
     total_lines: usize,
+    tokenizer: String,
+    dropped_for_memory: usize,
+    base_ref: Option<String>,
 }
 
 // --- Safety & Security Logic ---
@@ -146,6 +579,48 @@ fn detect_language(path: &Path) -> String {
     }.to_string()
 }
 
+/// ripgrep-style `--type` table: maps each language name already produced by `detect_language`
+/// to the globs that select it.
+const LANGUAGE_TYPES: &[(&str, &[&str])] = &[
+    ("rust", &["*.rs"]),
+    ("python", &["*.py", "*.pyi", "*.pyx"]),
+    ("javascript", &["*.js", "*.jsx"]),
+    ("typescript", &["*.ts", "*.tsx"]),
+    ("html", &["*.html"]),
+    ("css", &["*.css", "*.scss"]),
+    ("go", &["*.go"]),
+    ("java", &["*.java"]),
+    ("c", &["*.c", "*.h"]),
+    ("cpp", &["*.cpp", "*.hpp"]),
+    ("bash", &["*.sh", "*.bash"]),
+    ("markdown", &["*.md"]),
+    ("json", &["*.json"]),
+    ("toml", &["*.toml"]),
+    ("yaml", &["*.yaml", "*.yml"]),
+    ("sql", &["*.sql"]),
+    ("xml", &["*.xml"]),
+    // Bracket classes accept either case of the first letter, so a lowercase `dockerfile` matches
+    // too. Other casings (e.g. `DOCKERFILE`), which `detect_language` also maps to "dockerfile"
+    // since it lowercases the whole name, are not matched by these globs.
+    ("dockerfile", &["[Dd]ockerfile"]),
+    ("makefile", &["[Mm]akefile"]),
+];
+
+fn globs_for_type(name: &str) -> Result<&'static [&'static str]> {
+    LANGUAGE_TYPES
+        .iter()
+        .find(|(lang, _)| lang.eq_ignore_ascii_case(name))
+        .map(|(_, globs)| *globs)
+        .with_context(|| format!("Unknown --type '{}' (see --type-list)", name))
+}
+
+fn print_type_list() {
+    println!("Supported --type / --type-not values:\n");
+    for (name, globs) in LANGUAGE_TYPES {
+        println!("  {:<12} {}", name, globs.join(", "));
+    }
+}
+
 // --- Core Logic ---
 
 fn is_binary(content: &[u8]) -> bool {
@@ -154,7 +629,7 @@ fn is_binary(content: &[u8]) -> bool {
 }
 
 // UPDATED: Now returns Result to track errors, handles head/tail for large files, filters whitespace
-fn process_file(path: &Path, root: &Path) -> Result<Option<FileArtifact>, String> {
+fn process_file(path: &Path, root: &Path, tokenizer: &Tokenizer) -> Result<Option<FileArtifact>, String> {
     let metadata = path.metadata().map_err(|e| e.to_string())?;
     
     if metadata.len() == 0 {
@@ -214,7 +689,8 @@ fn process_file(path: &Path, root: &Path) -> Result<Option<FileArtifact>, String
     content_str = sanitize_content(&content_str);
 
     let lines = content_str.lines().count();
-    let token_estimate = content_str.len() / CHARS_PER_TOKEN;
+    let token_estimate = tokenizer.count_tokens(&content_str).map_err(|e| e.to_string())?;
+    let chunks = chunk_content(&content_str, tokenizer).map_err(|e| e.to_string())?;
 
     Ok(Some(FileArtifact {
         relative_path,
@@ -223,6 +699,7 @@ fn process_file(path: &Path, root: &Path) -> Result<Option<FileArtifact>, String
         content: content_str,
         token_estimate,
         is_truncated,
+        chunks,
     }))
 }
 
@@ -267,9 +744,232 @@ fn escape_xml(input: &str) -> String {
          .replace('\'', "&apos;")
 }
 
+// --- HTML Output ---
+
+#[derive(Default)]
+struct HtmlDirNode {
+    dirs: std::collections::BTreeMap<String, HtmlDirNode>,
+    files: Vec<String>, // relative_path of each file directly in this directory
+}
+
+fn anchor_id(relative_path: &str) -> String {
+    format!("file-{}", relative_path.replace(|c: char| !c.is_ascii_alphanumeric(), "-"))
+}
+
+fn build_html_tree(artifacts: &[FileArtifact]) -> HtmlDirNode {
+    let mut root = HtmlDirNode::default();
+    for artifact in artifacts {
+        let path = Path::new(&artifact.relative_path);
+        let mut node = &mut root;
+        let mut components: Vec<_> = path.components().collect();
+        let file_name = components.pop();
+        for component in components {
+            let name = component.as_os_str().to_string_lossy().to_string();
+            node = node.dirs.entry(name).or_default();
+        }
+        if file_name.is_some() {
+            node.files.push(artifact.relative_path.clone());
+        }
+    }
+    root
+}
+
+fn render_html_tree(node: &HtmlDirNode, name: &str, open: bool) -> String {
+    let mut body = String::new();
+    for (dir_name, child) in &node.dirs {
+        body.push_str(&render_html_tree(child, dir_name, false));
+    }
+    for file in &node.files {
+        let display_name = Path::new(file).file_name().unwrap_or_default().to_string_lossy();
+        body.push_str(&format!(
+            "<li class=\"cc-file\"><a href=\"#{}\">{}</a></li>\n",
+            anchor_id(file), escape_xml(&display_name)
+        ));
+    }
+    format!(
+        "<details{}><summary>📁 {}</summary><ul>{}</ul></details>\n",
+        if open { " open" } else { "" }, escape_xml(name), body
+    )
+}
+
+/// Maps `detect_language` output to a syntax name looked up in syntect's bundled (default)
+/// syntax set. Keeping this keyed off `language` (rather than the file extension) means an
+/// extension-less language like `makefile` still highlights. The default set has no TypeScript,
+/// TOML or Dockerfile syntax, so those names are not found and `highlight_to_html` renders such
+/// files as plain text.
+fn syntax_name_for_language(language: &str) -> &'static str {
+    match language {
+        "rust" => "Rust",
+        "python" => "Python",
+        "javascript" => "JavaScript",
+        "typescript" => "TypeScript",
+        "html" => "HTML",
+        "css" => "CSS",
+        "go" => "Go",
+        "java" => "Java",
+        "c" => "C",
+        "cpp" => "C++",
+        "bash" => "Bourne Again Shell (bash)",
+        "markdown" => "Markdown",
+        "json" => "JSON",
+        "toml" => "TOML",
+        "yaml" => "YAML",
+        "sql" => "SQL",
+        "xml" => "XML",
+        "dockerfile" => "Dockerfile",
+        "makefile" => "Makefile",
+        _ => "Plain Text",
+    }
+}
+
+fn highlight_to_html(content: &str, language: &str) -> String {
+    use syntect::html::highlighted_html_for_string;
+    use syntect::parsing::SyntaxSet;
+    use syntect::highlighting::ThemeSet;
+
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    let syntax_set = SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines);
+    let theme_set = THEME_SET.get_or_init(ThemeSet::load_defaults);
+
+    let syntax = syntax_set
+        .find_syntax_by_name(syntax_name_for_language(language))
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+    let theme = &theme_set.themes["InspiredGitHub"];
+
+    highlighted_html_for_string(content, syntax_set, syntax, theme)
+        .unwrap_or_else(|_| format!("<pre>{}</pre>", escape_xml(content)))
+}
+
+const HTML_STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; margin: 0; display: flex; color: #222; }
+nav { width: 280px; flex-shrink: 0; height: 100vh; overflow-y: auto; border-right: 1px solid #ddd; padding: 1rem; box-sizing: border-box; }
+main { flex: 1; padding: 1rem 2rem; max-width: 100%; overflow-x: auto; }
+nav details { margin-left: 0.75rem; }
+nav ul { list-style: none; padding-left: 0.75rem; margin: 0; }
+nav a { text-decoration: none; color: #0969da; }
+header.cc-meta { border-bottom: 1px solid #ddd; padding-bottom: 0.5rem; margin-bottom: 1rem; }
+section.cc-file { border-bottom: 1px solid #eee; padding-bottom: 1rem; margin-bottom: 1rem; }
+section.cc-file h3 { font-family: monospace; }
+.cc-truncated { color: #9a6700; font-weight: bold; }
+pre { padding: 0.75rem; border-radius: 6px; overflow-x: auto; }
+"#;
+
+const HTML_SCRIPT: &str = r#"
+document.querySelectorAll('nav a').forEach(a => {
+  a.addEventListener('click', () => {
+    const target = document.querySelector(a.getAttribute('href'));
+    if (target) target.scrollIntoView({ behavior: 'smooth' });
+  });
+});
+"#;
+
+/// Renders a single, self-contained HTML report: a collapsible file tree in a sidebar,
+/// syntax-highlighted sections for each file, and a metadata header. All CSS/JS is inlined
+/// so the artifact has no external dependencies.
+fn generate_html(root_name: &str, timestamp: &str, total_files: usize, total_tokens: usize, total_lines: usize, tokenizer_name: &str, base_ref: Option<&str>, artifacts: &[FileArtifact]) -> String {
+    let tree = build_html_tree(artifacts);
+    let tree_html = render_html_tree(&tree, root_name, true);
+    let base_ref_line = base_ref
+        .map(|r| format!("<p>Showing only files changed since <code>{}</code></p>", escape_xml(r)))
+        .unwrap_or_default();
+
+    let mut sections = String::new();
+    for artifact in artifacts {
+        let mut meta = format!("Language: {} | Lines: {} | Tokens: ~{}", artifact.language, artifact.lines, artifact.token_estimate);
+        if artifact.is_truncated {
+            meta.push_str(" | <span class=\"cc-truncated\">⚠️ TRUNCATED</span>");
+        }
+        sections.push_str(&format!(
+            "<section class=\"cc-file\" id=\"{}\">\n<h3>{}</h3>\n<p><em>{}</em></p>\n{}\n</section>\n",
+            anchor_id(&artifact.relative_path),
+            escape_xml(&artifact.relative_path),
+            meta,
+            highlight_to_html(&artifact.content, &artifact.language),
+        ));
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8">
+<title>Codebase Context: {root_name}</title>
+<style>{style}</style>
+</head>
+<body>
+<nav>{tree}</nav>
+<main>
+<header class="cc-meta">
+<h1>📦 Codebase Context: {root_name}</h1>
+<p>Generated on {timestamp} | Files: {total_files} | Lines: {total_lines} | Tokens: ~{total_tokens} ({tokenizer_name})</p>
+{base_ref_line}
+</header>
+{sections}
+</main>
+<script>{script}</script>
+</body>
+</html>
+"#,
+        root_name = escape_xml(root_name),
+        style = HTML_STYLE,
+        tree = tree_html,
+        timestamp = timestamp,
+        total_files = total_files,
+        total_lines = total_lines,
+        total_tokens = total_tokens,
+        tokenizer_name = escape_xml(tokenizer_name),
+        base_ref_line = base_ref_line,
+        sections = sections,
+        script = HTML_SCRIPT,
+    )
+}
+
+/// Runs `git diff --name-only --relative --diff-filter=d <since_ref>` from `root` and returns the
+/// changed files under `root` as absolute, canonicalized paths. `--diff-filter=d` drops deleted
+/// files since there's nothing left on disk to scan for them.
+fn changed_files_since(root: &Path, since_ref: &str) -> Result<HashSet<PathBuf>> {
+    // -z NUL-delimits the output and disables git's default C-quoting/octal-escaping of
+    // non-ASCII or special characters in paths, so we don't have to un-quote them ourselves.
+    // --relative makes the paths relative to `root` (not the repository top), so they can be
+    // joined onto `root` even when it is a subdirectory of the repository.
+    let output = std::process::Command::new("git")
+        .args(["diff", "--name-only", "--relative", "--diff-filter=d", "-z", since_ref])
+        .current_dir(root)
+        .output()
+        .context("Failed to run git diff (is this a git repository?)")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git diff --name-only --relative --diff-filter=d {} failed: {}",
+            since_ref,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(output
+        .stdout
+        .split(|&b| b == 0)
+        .filter(|chunk| !chunk.is_empty())
+        .filter_map(|chunk| root.join(String::from_utf8_lossy(chunk).as_ref()).canonicalize().ok())
+        .collect())
+}
+
 fn main() -> Result<()> {
-    let args = Args::parse();
-    
+    let (mut args, explicit) = parse_args_from(std::env::args_os());
+
+    if args.type_list {
+        print_type_list();
+        return Ok(());
+    }
+
+    let start_time = Instant::now();
+    let root_path = args.path.canonicalize().context("Failed to resolve path")?;
+
+    // --- Config: merge in `.codecontexter.toml` for any flag not explicitly set on the CLI ---
+    let file_config = load_file_config(&args, &root_path)?;
+    apply_file_config(&mut args, file_config, &explicit);
+
     // --- Security: Check output path safety ---
     let output_path_abs = if args.output.is_absolute() {
         args.output.clone()
@@ -278,9 +978,6 @@ fn main() -> Result<()> {
     };
     check_output_safety(&output_path_abs, args.force)?;
 
-    let start_time = Instant::now();
-    let root_path = args.path.canonicalize().context("Failed to resolve path")?;
-
     println!("🚀 Starting scan of: {}", root_path.display());
 
     // 1. Setup Excludes & Discovery
@@ -290,7 +987,12 @@ fn main() -> Result<()> {
     spinner.enable_steady_tick(std::time::Duration::from_millis(100));
 
     let mut override_builder = OverrideBuilder::new(&root_path);
-    let hard_coded_excludes = vec!["!*.env", "!*.env.*", "!*.pem", "!*.key", "!id_rsa", "!id_ed25519", "!*.p12", "!*.pfx"];
+    // Besides secrets, never re-ingest the tool's own state: `hidden(false)` below means
+    // dotfiles are walked, so without these the embedding cache and config file would get
+    // scanned back into the very output they informed.
+    let embed_cache_exclude = format!("!{}", EMBEDDING_CACHE_FILE);
+    let config_exclude = format!("!{}", CONFIG_FILE_NAME);
+    let hard_coded_excludes = vec!["!*.env", "!*.env.*", "!*.pem", "!*.key", "!id_rsa", "!id_ed25519", "!*.p12", "!*.pfx", embed_cache_exclude.as_str(), config_exclude.as_str()];
 
     for pattern in hard_coded_excludes {
         override_builder.add(pattern).context("Failed to add security exclude")?;
@@ -298,14 +1000,27 @@ fn main() -> Result<()> {
     for pattern in &args.exclude {
         override_builder.add(&format!("!{}", pattern)).context("Invalid exclude pattern")?;
     }
-    
+
+    // --type: whitelist globs for the requested languages only.
+    for type_name in &args.type_filter {
+        for glob in globs_for_type(type_name)? {
+            override_builder.add(glob).context("Invalid --type glob")?;
+        }
+    }
+    // --type-not: blacklist globs for the excluded languages.
+    for type_name in &args.type_not {
+        for glob in globs_for_type(type_name)? {
+            override_builder.add(&format!("!{}", glob)).context("Invalid --type-not glob")?;
+        }
+    }
+
     let overrides = override_builder.build().context("Failed to build exclude overrides")?;
 
     let mut collected_paths = Vec::new();
     // 3. Symlink Handling: explicitly disable following links
     let walker = WalkBuilder::new(&root_path)
-        .hidden(false) 
-        .git_ignore(true)
+        .hidden(false)
+        .git_ignore(!args.all_files)
         .follow_links(false) // FIX: Prevent symlink loops/duplication
         .overrides(overrides) 
         .build();
@@ -329,6 +1044,14 @@ fn main() -> Result<()> {
     }
     
     collected_paths.sort();
+
+    // --since: intersect the normal walk results with files changed relative to a git ref, so
+    // gitignore/security excludes still apply.
+    if let Some(since_ref) = &args.since {
+        let changed = changed_files_since(&root_path, since_ref)?;
+        collected_paths.retain(|p| p.canonicalize().map(|c| changed.contains(&c)).unwrap_or(false));
+    }
+
     spinner.finish_and_clear();
     println!("📂 Found {} files.", collected_paths.len());
 
@@ -342,28 +1065,63 @@ fn main() -> Result<()> {
         .unwrap()
         .progress_chars("#>-"));
 
+    // Process in batches instead of one big par_iter().collect() so `max_crawl_memory` bounds
+    // memory *during* the crawl: once the running total crosses the cap we stop reading further
+    // batches entirely, rather than reading everything and discarding the excess afterwards.
+    // `collected_paths` is sorted, so this favors files that come earlier in path order; file
+    // size plays no part in which files are kept.
+    let batch_size = rayon::current_num_threads().max(1) * 8;
+    let max_crawl_bytes = args.max_crawl_memory.map(|mb| mb * 1_000_000);
+    let mut artifacts = Vec::with_capacity(collected_paths.len());
+    let mut errors = Vec::new();
+    let mut crawl_bytes = 0u64;
+    let mut dropped_for_memory = 0usize;
+    let mut cap_hit = false;
+
     // 5. Better Error Tracking
     // We first map to a tuple of (path, result) to separate errors later
-    let results: Vec<_> = collected_paths
-        .par_iter()
-        .map(|path| {
-            let res = process_file(path, &root_path);
-            progress.inc(1);
-            (path, res)
-        })
-        .collect();
-    progress.finish_with_message("Processing complete");
+    for batch in collected_paths.chunks(batch_size) {
+        if cap_hit {
+            dropped_for_memory += batch.len();
+            progress.inc(batch.len() as u64);
+            continue;
+        }
 
-    let mut artifacts = Vec::with_capacity(results.len());
-    let mut errors = Vec::new();
+        let batch_results: Vec<_> = batch
+            .par_iter()
+            .map(|path| {
+                let res = process_file(path, &root_path, &args.tokenizer);
+                progress.inc(1);
+                (path, res)
+            })
+            .collect();
 
-    for (path, res) in results {
-        match res {
-            Ok(Some(artifact)) => artifacts.push(artifact),
-            Ok(None) => {} // Skipped (binary, empty, etc.)
-            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        for (path, res) in batch_results {
+            match res {
+                Ok(Some(artifact)) => {
+                    if let Some(cap) = max_crawl_bytes {
+                        if crawl_bytes > cap {
+                            cap_hit = true;
+                            dropped_for_memory += 1;
+                            continue;
+                        }
+                        crawl_bytes += artifact.content.len() as u64;
+                        if crawl_bytes > cap {
+                            cap_hit = true;
+                        }
+                    }
+                    artifacts.push(artifact);
+                }
+                Ok(None) => {} // Skipped (binary, empty, etc.)
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
         }
     }
+    progress.finish_with_message("Processing complete");
+
+    if dropped_for_memory > 0 {
+        println!("⚠️  Dropped {} file(s) after crossing the {}MB crawl memory cap.", dropped_for_memory, args.max_crawl_memory.unwrap_or(0));
+    }
 
     if !errors.is_empty() && args.verbose {
         eprintln!("⚠️  Encountered {} errors:", errors.len());
@@ -373,6 +1131,14 @@ fn main() -> Result<()> {
         if errors.len() > 10 { eprintln!("   ... and {} more.", errors.len() - 10); }
     }
 
+    // RAG Mode: if a query was given, narrow `artifacts` down to the most relevant chunks
+    // instead of dumping every file in full.
+    if let Some(query) = &args.query {
+        println!("🔍 Ranking content against query (budget: ~{} tokens)...", args.max_tokens);
+        artifacts = apply_rag_selection(artifacts, query, args.max_tokens, &root_path, &args.tokenizer)?;
+        println!("📎 Kept {} file(s) after relevance ranking.", artifacts.len());
+    }
+
     // 4. Aggregation & Output Streaming
     let total_tokens: usize = artifacts.iter().map(|a| a.token_estimate).sum();
     let total_lines: usize = artifacts.iter().map(|a| a.lines).sum();
@@ -386,7 +1152,10 @@ fn main() -> Result<()> {
     match args.format {
         OutputFormat::Markdown => {
             writeln!(writer, "# 📦 Codebase Context: {}", root_path.file_name().unwrap_or_default().to_string_lossy())?;
-            writeln!(writer, "> Generated on {} | Files: {} | Tokens: ~{}\n", timestamp, total_files, total_tokens)?;
+            writeln!(writer, "> Generated on {} | Files: {} | Tokens: ~{} ({})\n", timestamp, total_files, total_tokens, args.tokenizer.name())?;
+            if let Some(since_ref) = &args.since {
+                writeln!(writer, "> Showing only files changed since `{}`\n", since_ref)?;
+            }
             writeln!(writer, "## 🌲 Project Structure\n```text\n{}\n```\n", file_tree)?;
             writeln!(writer, "## 📄 File Contents")?;
             
@@ -409,6 +1178,9 @@ fn main() -> Result<()> {
                     total_files,
                     total_tokens,
                     total_lines,
+                    tokenizer: args.tokenizer.name().to_string(),
+                    dropped_for_memory,
+                    base_ref: args.since.clone(),
                 },
                 project_tree: &file_tree,
                 files: &artifacts,
@@ -420,7 +1192,13 @@ fn main() -> Result<()> {
             writeln!(writer, "  <metadata>\n    <root_path>{}</root_path>", escape_xml(&root_path.to_string_lossy()))?;
             writeln!(writer, "    <generated_at>{}</generated_at>", timestamp)?;
             writeln!(writer, "    <total_files>{}</total_files>", total_files)?;
-            writeln!(writer, "    <total_tokens>{}</total_tokens>\n  </metadata>", total_tokens)?;
+            writeln!(writer, "    <total_tokens>{}</total_tokens>", total_tokens)?;
+            writeln!(writer, "    <tokenizer>{}</tokenizer>", escape_xml(args.tokenizer.name()))?;
+            writeln!(writer, "    <dropped_for_memory>{}</dropped_for_memory>", dropped_for_memory)?;
+            if let Some(since_ref) = &args.since {
+                writeln!(writer, "    <base_ref>{}</base_ref>", escape_xml(since_ref))?;
+            }
+            writeln!(writer, "  </metadata>")?;
             
             writeln!(writer, "  <project_tree>\n{}\n  </project_tree>", escape_xml(&file_tree))?;
             
@@ -436,6 +1214,11 @@ fn main() -> Result<()> {
                 writeln!(writer, "    </file>")?;
             }
             writeln!(writer, "  </files>\n</codebase>")?;
+        },
+        OutputFormat::Html => {
+            let root_name = root_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let html = generate_html(&root_name, &timestamp, total_files, total_tokens, total_lines, args.tokenizer.name(), args.since.as_deref(), &artifacts);
+            writer.write_all(html.as_bytes())?;
         }
     }
     
@@ -467,4 +1250,104 @@ fn main() -> Result<()> {
 
     println!("⏱️  Time taken: {:.2?}", start_time.elapsed());
     Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cosine_similarity_identical_vectors_is_one() {
+        let v = [1.0, 2.0, 3.0];
+        assert!((cosine_similarity(&v, &v) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cosine_similarity_orthogonal_vectors_is_zero() {
+        let a = [1.0, 0.0];
+        let b = [0.0, 1.0];
+        assert!((cosine_similarity(&a, &b)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cosine_similarity_zero_vector_is_zero() {
+        let a = [0.0, 0.0, 0.0];
+        let b = [1.0, 2.0, 3.0];
+        assert_eq!(cosine_similarity(&a, &b), 0.0);
+    }
+
+    #[test]
+    fn select_within_budget_drops_chunks_after_the_one_that_exceeds_budget() {
+        // Greedy in rank order: 400 (sum 400, kept) -> 300 (sum 700, kept) -> 500 (sum still
+        // <=1000 *before* this chunk, so it's kept too, taking the sum to 1200) -> 200 (sum is
+        // now over budget, so this one is dropped).
+        let kept = select_within_budget(&[400, 300, 500, 200], 1000);
+        assert_eq!(kept, vec![true, true, true, false]);
+    }
+
+    #[test]
+    fn select_within_budget_keeps_everything_under_budget() {
+        let kept = select_within_budget(&[10, 20, 30], 1000);
+        assert_eq!(kept, vec![true, true, true]);
+    }
+
+    #[test]
+    fn select_within_budget_always_keeps_first_chunk() {
+        // Even a single chunk larger than the budget is kept; the cap only stops *later* additions.
+        let kept = select_within_budget(&[5000], 1000);
+        assert_eq!(kept, vec![true]);
+    }
+
+    #[test]
+    fn apply_file_config_fills_in_defaults_left_untouched_on_cli() {
+        let (mut args, explicit) = parse_args_from(["codecontexter"]);
+        let config = FileConfig {
+            output: Some(PathBuf::from("custom.md")),
+            format: Some(OutputFormat::Json),
+            max_tokens: Some(4000),
+            ..Default::default()
+        };
+        apply_file_config(&mut args, config, &explicit);
+        assert_eq!(args.output, PathBuf::from("custom.md"));
+        assert_eq!(args.format, OutputFormat::Json);
+        assert_eq!(args.max_tokens, 4000);
+    }
+
+    #[test]
+    fn apply_file_config_explicit_cli_flags_win_over_config() {
+        let (mut args, explicit) =
+            parse_args_from(["codecontexter", "--output", "explicit.md", "--max-tokens", "2000"]);
+        let config = FileConfig {
+            output: Some(PathBuf::from("from_config.md")),
+            max_tokens: Some(4000),
+            ..Default::default()
+        };
+        apply_file_config(&mut args, config, &explicit);
+        assert_eq!(args.output, PathBuf::from("explicit.md"));
+        assert_eq!(args.max_tokens, 2000);
+    }
+
+    #[test]
+    fn apply_file_config_explicit_cli_flag_wins_even_when_value_matches_the_default() {
+        // Regression test: the user explicitly chose `--format markdown`, which happens to be
+        // the same value as the built-in default, while the config sets `format = "json"`. The
+        // CLI choice must still win — comparing the parsed value against the hardcoded default
+        // can't tell these two cases apart, only `ExplicitFlags` (from clap's `value_source`) can.
+        let (mut args, explicit) = parse_args_from(["codecontexter", "--format", "markdown"]);
+        let config = FileConfig { format: Some(OutputFormat::Json), ..Default::default() };
+        apply_file_config(&mut args, config, &explicit);
+        assert_eq!(args.format, OutputFormat::Markdown);
+    }
+
+    #[test]
+    fn apply_file_config_crawl_section_respects_explicit_all_files() {
+        let (mut args, explicit) = parse_args_from(["codecontexter", "--all-files"]);
+        let config = FileConfig {
+            crawl: Some(CrawlConfig { max_crawl_memory: Some(500), all_files: Some(false) }),
+            ..Default::default()
+        };
+        apply_file_config(&mut args, config, &explicit);
+        assert_eq!(args.max_crawl_memory, Some(500));
+        assert!(args.all_files);
+    }
 }
\ No newline at end of file